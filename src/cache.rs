@@ -0,0 +1,134 @@
+//! Local persistent cache for `DatabaseClient`, gated behind the `cache`
+//! feature. Wraps a `sled` tree the same way MeiliSearch/Conduit wrap an
+//! embedded KV store in front of their real backend: Celestia stays the
+//! source of truth, the cache just avoids re-fetching blobs we've already
+//! seen.
+
+use crate::schema::{DatabaseError, Record};
+
+/// Key the synced-head marker is stored under within its own tree.
+const HEAD_KEY: &[u8] = b"head";
+
+pub struct LocalCache {
+    db: sled::Db,
+    /// Keys known to be deleted, kept in a separate tree so a cold-started
+    /// client can rebuild `DatabaseMetadata.deleted` without re-scanning
+    /// the backend for tombstones.
+    tombstones: sled::Tree,
+    /// The synced-head marker, kept in its own tree so a record legitimately
+    /// keyed `"__head"` in the records tree can never collide with it.
+    head: sled::Tree,
+}
+
+impl LocalCache {
+    /// Opens (or creates) a local cache rooted at `path`.
+    pub fn open(path: &str) -> Result<Self, DatabaseError> {
+        let db = sled::open(path)
+            .map_err(|e| DatabaseError::DatabaseError(format!("failed to open cache: {}", e)))?;
+        let tombstones = db.open_tree("tombstones")
+            .map_err(|e| DatabaseError::DatabaseError(format!("failed to open cache: {}", e)))?;
+        let head = db.open_tree("head")
+            .map_err(|e| DatabaseError::DatabaseError(format!("failed to open cache: {}", e)))?;
+        Ok(Self { db, tombstones, head })
+    }
+
+    /// Looks up a cached `(height, record)` pair for `key`.
+    pub fn get_record(&self, key: &str) -> Result<Option<(u64, Record)>, DatabaseError> {
+        match self.db.get(key.as_bytes())
+            .map_err(|e| DatabaseError::DatabaseError(format!("cache read failed: {}", e)))? {
+            Some(bytes) => {
+                let entry: CacheEntry = serde_json::from_slice(&bytes)
+                    .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
+                Ok(Some((entry.height, entry.record)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Caches `record` as living at `height`.
+    pub fn put_record(&self, key: &str, height: u64, record: &Record) -> Result<(), DatabaseError> {
+        let entry = CacheEntry { height, record: record.clone() };
+        let bytes = serde_json::to_vec(&entry)
+            .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
+        self.db.insert(key.as_bytes(), bytes)
+            .map_err(|e| DatabaseError::DatabaseError(format!("cache write failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// Evicts a cached record, e.g. after it's been deleted.
+    pub fn remove_record(&self, key: &str) -> Result<(), DatabaseError> {
+        self.db.remove(key.as_bytes())
+            .map_err(|e| DatabaseError::DatabaseError(format!("cache write failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// Marks `key` as deleted, so a cold-started client can tell it was
+    /// tombstoned without re-fetching from the backend.
+    pub fn mark_deleted(&self, key: &str) -> Result<(), DatabaseError> {
+        self.tombstones.insert(key.as_bytes(), &[])
+            .map_err(|e| DatabaseError::DatabaseError(format!("cache write failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// Clears a prior tombstone for `key`, e.g. because it was written
+    /// again under the same key.
+    pub fn clear_deleted(&self, key: &str) -> Result<(), DatabaseError> {
+        self.tombstones.remove(key.as_bytes())
+            .map_err(|e| DatabaseError::DatabaseError(format!("cache write failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// Every key this cache has recorded as deleted.
+    pub fn deleted_keys(&self) -> Result<Vec<String>, DatabaseError> {
+        let mut keys = Vec::new();
+        for item in self.tombstones.iter() {
+            let (key, _) = item
+                .map_err(|e| DatabaseError::DatabaseError(format!("cache read failed: {}", e)))?;
+            keys.push(String::from_utf8_lossy(&key).into_owned());
+        }
+        Ok(keys)
+    }
+
+    /// The last Celestia height this cache has fully synced up to.
+    pub fn head(&self) -> Result<Option<u64>, DatabaseError> {
+        match self.head.get(HEAD_KEY)
+            .map_err(|e| DatabaseError::DatabaseError(format!("cache read failed: {}", e)))? {
+            Some(bytes) => {
+                let height = u64::from_be_bytes(bytes.as_ref().try_into().map_err(|_| {
+                    DatabaseError::DatabaseError("corrupt cached head height".to_string())
+                })?);
+                Ok(Some(height))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Records the last height this cache has synced up to.
+    pub fn set_head(&self, height: u64) -> Result<(), DatabaseError> {
+        self.head.insert(HEAD_KEY, &height.to_be_bytes())
+            .map_err(|e| DatabaseError::DatabaseError(format!("cache write failed: {}", e)))?;
+        Ok(())
+    }
+
+    /// All cached `(key, height, data size)` triples, used to warm a fresh
+    /// index on startup without hitting Celestia.
+    pub fn entries(&self) -> Result<Vec<(String, u64, u64)>, DatabaseError> {
+        let mut entries = Vec::new();
+        for item in self.db.iter() {
+            let (key, bytes) = item
+                .map_err(|e| DatabaseError::DatabaseError(format!("cache read failed: {}", e)))?;
+            let key = String::from_utf8_lossy(&key).into_owned();
+            let entry: CacheEntry = serde_json::from_slice(&bytes)
+                .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
+            let size = entry.record.data.len() as u64;
+            entries.push((key, entry.height, size));
+        }
+        Ok(entries)
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CacheEntry {
+    height: u64,
+    record: Record,
+}
@@ -1,15 +1,41 @@
-use celestia_rpc::{BlobClient, Client, HeaderClient};
-use celestia_types::{nmt::Namespace, Blob, AppVersion};
+use celestia_rpc::Client;
+use celestia_types::nmt::Namespace;
 use serde_json;
 use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
 
-use crate::schema::{DatabaseError, DatabaseMetadata, Record};
+use crate::migrations::Migrator;
+use crate::schema::{DatabaseError, DatabaseMetadata, Record, Tombstone};
+use crate::storage::{CelestiaBackend, StorageBackend};
+#[cfg(feature = "cache")]
+use crate::cache::LocalCache;
 
-pub struct DatabaseClient {
-    client: Client,
-    namespace: Namespace,
+/// How often `subscribe` polls the backend for a new head height.
+const SUBSCRIBE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Per-namespace caps enforced by `add_record`/`update_record`. `None`
+/// means unlimited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quota {
+    pub max_records: Option<u64>,
+    pub max_bytes: Option<u64>,
+}
+
+pub struct DatabaseClient<B: StorageBackend> {
+    backend: B,
     metadata: Option<DatabaseMetadata>,
     search_limit: Option<u64>,
+    quota: Quota,
+    /// Highest height this client has already scanned for records; used by
+    /// `subscribe` to only report newly-included ones.
+    last_seen_height: u64,
+    /// Upgrades records written under an older schema as they're read
+    /// back. Empty by default, meaning this binary's `CURRENT_SCHEMA_VERSION`
+    /// is the only schema it knows about.
+    migrator: Migrator,
+    #[cfg(feature = "cache")]
+    cache: Option<LocalCache>,
 }
 
 // Helper function to get current timestamp for logging
@@ -18,12 +44,14 @@ fn log_with_timestamp(message: &str) {
     println!("[{}] {}", timestamp, message);
 }
 
-impl DatabaseClient {
-    pub async fn new(
-        client: Client, 
-        namespace_bytes: Vec<u8>, 
-        _start_height: Option<u64>, // Kept for API compatibility but ignored
-        search_limit: Option<u64>
+impl DatabaseClient<CelestiaBackend> {
+    /// Connects to a running Celestia node and scopes the database to
+    /// `namespace_bytes`.
+    pub async fn connect_celestia(
+        client: Client,
+        namespace_bytes: Vec<u8>,
+        search_limit: Option<u64>,
+        quota: Quota,
     ) -> Result<Self, DatabaseError> {
         if namespace_bytes.len() != 10 {
             return Err(DatabaseError::InvalidNamespace("Namespace must be exactly 10 bytes".to_string()));
@@ -32,11 +60,41 @@ impl DatabaseClient {
         let namespace = Namespace::new(0, &namespace_bytes)
             .map_err(|e| DatabaseError::InvalidNamespace(e.to_string()))?;
 
+        Self::new(CelestiaBackend::new(client, namespace), search_limit, quota).await
+    }
+
+    /// Like [`connect_celestia`](Self::connect_celestia), but backs reads
+    /// with a local persistent cache rooted at `cache_path`.
+    #[cfg(feature = "cache")]
+    pub async fn connect_celestia_with_cache(
+        client: Client,
+        namespace_bytes: Vec<u8>,
+        search_limit: Option<u64>,
+        quota: Quota,
+        cache_path: &str,
+    ) -> Result<Self, DatabaseError> {
+        if namespace_bytes.len() != 10 {
+            return Err(DatabaseError::InvalidNamespace("Namespace must be exactly 10 bytes".to_string()));
+        }
+
+        let namespace = Namespace::new(0, &namespace_bytes)
+            .map_err(|e| DatabaseError::InvalidNamespace(e.to_string()))?;
+
+        Self::with_cache(CelestiaBackend::new(client, namespace), search_limit, quota, cache_path).await
+    }
+}
+
+impl<B: StorageBackend> DatabaseClient<B> {
+    pub async fn new(backend: B, search_limit: Option<u64>, quota: Quota) -> Result<Self, DatabaseError> {
         let mut db_client = Self {
-            client,
-            namespace,
+            backend,
             metadata: None,
             search_limit,
+            quota,
+            last_seen_height: 0,
+            migrator: Migrator::default(),
+            #[cfg(feature = "cache")]
+            cache: None,
         };
 
         // Try to discover existing database within search_limit blocks
@@ -45,44 +103,133 @@ impl DatabaseClient {
             db_client.metadata = Some(metadata);
         } else {
             // Create new database at current height
-            let latest_height = db_client.client.header_local_head()
-                .await
-                .map_err(|e| DatabaseError::CelestiaError(e.to_string()))?
-                .height()
-                .value();
-            
-            let metadata = DatabaseMetadata {
-                start_height: latest_height,
-                record_count: 0,
-                last_updated: chrono::Utc::now(),
-            };
-            
+            let latest_height = db_client.backend.head().await?;
+
+            let mut metadata = DatabaseMetadata::new(latest_height);
+            metadata.schema_version = db_client.migrator.target_version();
+
             log_with_timestamp(&format!("Creating new database (estimating height {})", latest_height));
-            
+
             // Save metadata and get the actual inclusion height
             let actual_height = db_client.save_metadata(&metadata).await?;
-            
+
             // Update metadata with actual height
-            let updated_metadata = DatabaseMetadata {
-                start_height: actual_height,
-                record_count: 0,
-                last_updated: chrono::Utc::now(),
-            };
-            
+            let mut updated_metadata = DatabaseMetadata::new(actual_height);
+            updated_metadata.schema_version = db_client.migrator.target_version();
+
             log_with_timestamp(&format!("Database created at height {}", actual_height));
             db_client.metadata = Some(updated_metadata);
         }
 
+        db_client.last_seen_height = db_client.backend.head().await?;
+
         Ok(db_client)
     }
 
+    /// Registers the migration pipeline used to upgrade records written
+    /// under an older schema as they're read back
+    pub fn with_migrator(mut self, migrator: Migrator) -> Self {
+        self.migrator = migrator;
+        self
+    }
+
+    /// Opens a `DatabaseClient` backed by a local persistent cache rooted at
+    /// `cache_path`. Instead of scanning the backend for the latest
+    /// metadata blob, the index is warmed from whatever the cache already
+    /// knows about, then `sync` pulls anything newer straight from the
+    /// backend.
+    #[cfg(feature = "cache")]
+    pub async fn with_cache(
+        backend: B,
+        search_limit: Option<u64>,
+        quota: Quota,
+        cache_path: &str,
+    ) -> Result<Self, DatabaseError> {
+        let cache = LocalCache::open(cache_path)?;
+        let cached_head = cache.head()?.unwrap_or(0);
+
+        let mut metadata = DatabaseMetadata::new(if cached_head > 0 { cached_head } else { 1 });
+        metadata.schema_version = Migrator::default().target_version();
+        for (key, height, size) in cache.entries()? {
+            metadata.add_record(key, height, size);
+        }
+        let deleted_keys = cache.deleted_keys()?;
+        for key in &deleted_keys {
+            if !metadata.delete_record(key) {
+                metadata.deleted.insert(key.clone());
+            }
+        }
+
+        log_with_timestamp(&format!(
+            "Warmed index with {} cached records, {} tombstones (cached head: {})",
+            metadata.record_count, deleted_keys.len(), cached_head
+        ));
+
+        let mut db_client = Self {
+            backend,
+            metadata: Some(metadata),
+            search_limit,
+            quota,
+            last_seen_height: 0,
+            migrator: Migrator::default(),
+            cache: Some(cache),
+        };
+
+        db_client.sync().await?;
+        db_client.last_seen_height = db_client.backend.head().await?;
+
+        Ok(db_client)
+    }
+
+    /// Pulls any blobs in `[cached_head..latest_height]` into the local
+    /// cache, folding record blobs into the index and adopting any newer
+    /// metadata snapshot. A no-op if this client wasn't built with a cache.
+    #[cfg(feature = "cache")]
+    pub async fn sync(&mut self) -> Result<(), DatabaseError> {
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return Ok(()),
+        };
+
+        let cached_head = cache.head()?.unwrap_or(0);
+        let latest_height = self.backend.head().await?;
+
+        if latest_height <= cached_head {
+            return Ok(());
+        }
+
+        log_with_timestamp(&format!("Syncing cache from height {} to {}", cached_head + 1, latest_height));
+
+        for height in (cached_head + 1)..=latest_height {
+            let blobs = match self.backend.get_at(height).await {
+                Ok(blobs) => blobs,
+                Err(_) => continue,
+            };
+
+            for payload in &blobs {
+                if let Ok(metadata) = serde_json::from_slice::<DatabaseMetadata>(payload) {
+                    let is_newer = self.metadata.as_ref()
+                        .map(|current| metadata.last_updated > current.last_updated)
+                        .unwrap_or(true);
+                    if is_newer {
+                        self.metadata = Some(metadata);
+                    }
+                } else if let Ok(record) = serde_json::from_slice::<Record>(payload) {
+                    cache.put_record(&record.key, height, &record)?;
+                    if let Some(metadata) = &mut self.metadata {
+                        metadata.add_record(record.key.clone(), height, record.data.len() as u64);
+                    }
+                }
+            }
+        }
+
+        cache.set_head(latest_height)?;
+        Ok(())
+    }
+
     async fn discover_database(&self) -> Result<Option<DatabaseMetadata>, DatabaseError> {
-        let latest_height = self.client.header_local_head()
-            .await
-            .map_err(|e| DatabaseError::CelestiaError(e.to_string()))?
-            .height()
-            .value();
-        
+        let latest_height = self.backend.head().await?;
+
         // Determine search range based on search_limit
         let start_height = if let Some(limit) = self.search_limit {
             if latest_height > limit {
@@ -98,155 +245,326 @@ impl DatabaseClient {
                 1
             }
         };
-        
+
         log_with_timestamp(&format!("Searching for existing database (blocks {}..{})", start_height, latest_height));
-        
-        // Search for metadata
+
+        // Walk the whole range and keep the most recently updated metadata
+        // blob we find, since the index is only correct in the latest
+        // snapshot and an older metadata blob may still live at a height
+        // we visit after it while scanning backward.
+        let mut best: Option<DatabaseMetadata> = None;
+
         for height in (start_height..=latest_height).rev() {
-            match self.get_blobs_at_height(height).await {
+            match self.backend.get_at(height).await {
                 Ok(blobs) => {
-                    for blob in blobs {
+                    for payload in &blobs {
                         // Try to parse as metadata
-                        if let Ok(metadata) = serde_json::from_slice::<DatabaseMetadata>(&blob.data) {
-                            return Ok(Some(metadata));
+                        if let Ok(metadata) = serde_json::from_slice::<DatabaseMetadata>(payload) {
+                            let is_newer = best
+                                .as_ref()
+                                .map(|current| metadata.last_updated > current.last_updated)
+                                .unwrap_or(true);
+                            if is_newer {
+                                best = Some(metadata);
+                            }
                         }
                     }
                 }
                 Err(_) => continue,
             }
         }
-        
-        log_with_timestamp("No existing database found within search range");
-        Ok(None)
+
+        if let Some(metadata) = &best {
+            log_with_timestamp(&format!("Latest database snapshot updated at {}", metadata.last_updated));
+            if metadata.schema_version > self.migrator.target_version() {
+                log_with_timestamp(&format!(
+                    "Warning: found schema_version {} but this binary only understands up to {}; upgrade before writing",
+                    metadata.schema_version, self.migrator.target_version()
+                ));
+            }
+        } else {
+            log_with_timestamp("No existing database found within search range");
+        }
+
+        Ok(best)
     }
-    
-    async fn save_metadata(&self, metadata: &DatabaseMetadata) -> Result<u64, DatabaseError> {
-        let metadata_json = serde_json::to_vec(metadata)
-            .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
 
-        let blob = Blob::new(
-            self.namespace.clone(),
-            metadata_json,
-            AppVersion::V2,
-        ).map_err(|e| DatabaseError::CelestiaError(e.to_string()))?;
+    /// Rejects a write that would push this namespace past its configured
+    /// quota. `key` already existing doesn't count against `max_records`
+    /// (it's a replacement, not a new slot), and `max_bytes` is checked
+    /// against the size the write would settle at, not just added on top.
+    fn check_quota(&self, key: &str, incoming_bytes: u64) -> Result<(), DatabaseError> {
+        let metadata = match &self.metadata {
+            Some(metadata) => metadata,
+            None => return Ok(()),
+        };
 
-        let response = self.client.blob_submit(&[blob], Default::default())
-            .await
-            .map_err(|e| DatabaseError::CelestiaError(e.to_string()))?;
-            
-        // Debug the response content
-        log_with_timestamp(&format!("Submit response: {:?}", response));
-        
-        // Try to access height using different approaches
-        let inclusion_height = if let Ok(serde_value) = serde_json::to_value(&response) {
-            if let Some(height_value) = serde_value.get("height") {
-                if let Some(height_num) = height_value.as_u64() {
-                    Some(height_num)
-                } else {
-                    None
+        let is_new_key = !metadata.index.contains_key(key);
+
+        if is_new_key {
+            if let Some(max_records) = self.quota.max_records {
+                if metadata.record_count >= max_records {
+                    return Err(DatabaseError::QuotaExceeded(format!(
+                        "record count quota of {} reached", max_records
+                    )));
                 }
-            } else {
-                None
             }
-        } else {
-            None
-        };
-        
-        // Get the current height as fallback
-        let current_height = self.client.header_local_head()
-            .await
-            .map_err(|e| DatabaseError::CelestiaError(e.to_string()))?
-            .height()
-            .value();
-            
-        // Use the inclusion height if available, otherwise use current height
-        let final_height = inclusion_height.unwrap_or(current_height);
-        log_with_timestamp(&format!("Using height: {}", final_height));
-        
-        Ok(final_height)
-    }
-
-    async fn get_blobs_at_height(&self, height: u64) -> Result<Vec<Blob>, DatabaseError> {
-        self.client.blob_get_all(height, &[self.namespace.clone()])
-            .await
-            .map_err(|e| DatabaseError::CelestiaError(e.to_string()))?
-            .ok_or_else(|| DatabaseError::CelestiaError("No blobs found".to_string()))
+        }
+
+        if let Some(max_bytes) = self.quota.max_bytes {
+            let existing_size = metadata.sizes.get(key).copied().unwrap_or(0);
+            let projected_bytes = (metadata.total_bytes + incoming_bytes).saturating_sub(existing_size);
+            if projected_bytes > max_bytes {
+                return Err(DatabaseError::QuotaExceeded(format!(
+                    "total byte quota of {} would be exceeded ({} bytes)", max_bytes, projected_bytes
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Recomputes `record_count`, `total_bytes`, and `index` from the
+    /// actual blobs in the namespace and persists an authoritative
+    /// metadata snapshot. Use this if counters have drifted from reality,
+    /// e.g. after a partial submit failure or a stale in-memory client.
+    pub async fn repair_counters(&mut self) -> Result<(), DatabaseError> {
+        let latest_height = self.backend.head().await?;
+        let start_height = self.metadata.as_ref().map(|m| m.start_height).unwrap_or(1);
+
+        let mut fresh = DatabaseMetadata::new(start_height);
+        fresh.schema_version = self.migrator.target_version();
+        let mut seen = std::collections::HashSet::new();
+
+        // Scan newest-first so the first time we see a key, we're looking
+        // at its most recent state — a tombstone or a value.
+        for height in (start_height..=latest_height).rev() {
+            let payloads = match self.backend.get_at(height).await {
+                Ok(payloads) => payloads,
+                Err(_) => continue,
+            };
+
+            for payload in &payloads {
+                if let Ok(record) = serde_json::from_slice::<Record>(payload) {
+                    if seen.insert(record.key.clone()) {
+                        fresh.add_record(record.key, height, record.data.len() as u64);
+                    }
+                } else if let Ok(tombstone) = serde_json::from_slice::<Tombstone>(payload) {
+                    if seen.insert(tombstone.key.clone()) {
+                        fresh.deleted.insert(tombstone.key);
+                    }
+                }
+            }
+        }
+
+        log_with_timestamp(&format!(
+            "repair_counters: recomputed {} records, {} bytes",
+            fresh.record_count, fresh.total_bytes
+        ));
+
+        self.save_metadata(&fresh).await?;
+        self.metadata = Some(fresh);
+
+        Ok(())
     }
 
-    pub async fn add_record(&mut self, record: Record) -> Result<(), DatabaseError> {
-        let mut blobs = Vec::new();
+    async fn save_metadata(&self, metadata: &DatabaseMetadata) -> Result<u64, DatabaseError> {
+        let metadata_json = serde_json::to_vec(metadata)
+            .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
+
+        self.backend.submit(vec![metadata_json]).await
+    }
+
+    pub async fn add_record(&mut self, mut record: Record) -> Result<(), DatabaseError> {
+        self.check_quota(&record.key, record.data.len() as u64)?;
+
+        // Stamp the record at this client's live target version, not just
+        // whatever `Record::new` compiled in as `CURRENT_SCHEMA_VERSION` —
+        // otherwise `Migrator::migrate` can't tell a freshly-written record
+        // apart from one that actually needs upgrading.
+        record.schema_version = self.migrator.target_version();
 
-        // Prepare record blob
         let record_json = serde_json::to_vec(&record)
             .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
-        let record_blob = Blob::new(
-            self.namespace.clone(),
-            record_json,
-            AppVersion::V2,
-        ).map_err(|e| DatabaseError::CelestiaError(e.to_string()))?;
-        blobs.push(record_blob);
-
-        // Prepare metadata blob if needed
+
+        // Submit the record on its own so we learn the exact height it was
+        // included at, then fold that height into the index before
+        // persisting an updated metadata snapshot.
+        let record_height = self.backend.submit(vec![record_json]).await?;
+
         if let Some(metadata) = &self.metadata {
             let mut updated_metadata = metadata.clone();
-            updated_metadata.record_count += 1;
-            updated_metadata.last_updated = chrono::Utc::now();
-            
-            let metadata_json = serde_json::to_vec(&updated_metadata)
-                .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
-            let metadata_blob = Blob::new(
-                self.namespace.clone(),
-                metadata_json,
-                AppVersion::V2,
-            ).map_err(|e| DatabaseError::CelestiaError(e.to_string()))?;
-            blobs.push(metadata_blob);
-
-            // Update in-memory metadata
+            updated_metadata.add_record(record.key.clone(), record_height, record.data.len() as u64);
+            updated_metadata.schema_version = self.migrator.target_version();
+
+            self.save_metadata(&updated_metadata).await?;
             self.metadata = Some(updated_metadata);
         }
 
-        // Submit both blobs in a single transaction
-        self.client.blob_submit(&blobs, Default::default())
-            .await
-            .map_err(|e| DatabaseError::CelestiaError(e.to_string()))?;
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.cache {
+            cache.put_record(&record.key, record_height, &record)?;
+            cache.clear_deleted(&record.key)?;
+            cache.set_head(record_height)?;
+        }
 
         Ok(())
     }
 
+    /// Submits a new version of an existing record. Fails with
+    /// `RecordNotFound` if `key` doesn't currently exist.
+    pub async fn update_record(&mut self, key: &str, data: Vec<u8>) -> Result<(), DatabaseError> {
+        let mut record = self.get_record(key).await?
+            .ok_or_else(|| DatabaseError::RecordNotFound(key.to_string()))?;
+        record.update(data);
+
+        // Record blobs are immutable, so an update is really just another
+        // add_record whose height replaces the key's index entry.
+        self.add_record(record).await
+    }
+
+    /// Submits a tombstone for `key` and marks it deleted in the index, so
+    /// future `get_record`/`list_records` calls skip it and the most
+    /// recent tombstone wins over any older value during a raw scan.
+    pub async fn delete_record(&mut self, key: &str) -> Result<(), DatabaseError> {
+        let tombstone = Tombstone::new(key.to_string());
+        let tombstone_json = serde_json::to_vec(&tombstone)
+            .map_err(|e| DatabaseError::SerializationError(e.to_string()))?;
+        self.backend.submit(vec![tombstone_json]).await?;
+
+        if let Some(metadata) = &self.metadata {
+            let mut updated_metadata = metadata.clone();
+            updated_metadata.delete_record(key);
+            updated_metadata.schema_version = self.migrator.target_version();
+
+            self.save_metadata(&updated_metadata).await?;
+            self.metadata = Some(updated_metadata);
+        }
+
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.cache {
+            cache.remove_record(key)?;
+            cache.mark_deleted(key)?;
+        }
+
+        Ok(())
+    }
+
+    /// Rewrites a fresh metadata snapshot with tombstoned keys dropped
+    /// from the index, so `discover_database` doesn't keep paying for
+    /// dead keys as the database grows.
+    pub async fn compact(&mut self) -> Result<(), DatabaseError> {
+        let metadata = match &self.metadata {
+            Some(metadata) => metadata,
+            None => return Ok(()),
+        };
+
+        let mut compacted = metadata.clone();
+        for key in &metadata.deleted {
+            compacted.index.remove(key);
+        }
+        compacted.last_updated = chrono::Utc::now();
+        compacted.schema_version = self.migrator.target_version();
+
+        self.save_metadata(&compacted).await?;
+        self.metadata = Some(compacted);
+
+        Ok(())
+    }
+
+    /// Looks up `key`, transparently upgrading the record to
+    /// `self.migrator.target_version()` in memory if it was written under
+    /// an older schema. The upgrade isn't persisted; use
+    /// [`get_and_upgrade_record`](Self::get_and_upgrade_record) if you want
+    /// future reads to see the new schema version directly.
     pub async fn get_record(&self, key: &str) -> Result<Option<Record>, DatabaseError> {
-        let latest_height = self.client.header_local_head()
-            .await
-            .map_err(|e| DatabaseError::CelestiaError(e.to_string()))?
-            .height()
-            .value();
-        
-        // Get start height from metadata if available
-        let start_height = if let Some(metadata) = &self.metadata {
-            metadata.start_height
-        } else {
-            1 // Fallback to beginning if no metadata (shouldn't happen)
+        let mut record = self.get_record_raw(key).await?;
+        if let Some(record) = &mut record {
+            self.migrator.migrate(record);
+        }
+        Ok(record)
+    }
+
+    /// Like [`get_record`](Self::get_record), but if the record was
+    /// upgraded in memory, re-submits it so future reads see the new
+    /// schema version directly instead of migrating on every read.
+    pub async fn get_and_upgrade_record(&mut self, key: &str) -> Result<Option<Record>, DatabaseError> {
+        let mut record = match self.get_record_raw(key).await? {
+            Some(record) => record,
+            None => return Ok(None),
         };
-        
-        log_with_timestamp(&format!(
-            "Searching for record with key '{}' (database start: {}, current height: {})", 
-            key, start_height, latest_height
-        ));
-        
-        // Search from start height to the latest height
+
+        if self.migrator.migrate(&mut record) {
+            log_with_timestamp(&format!("Upgraded record '{}' to schema v{}", key, record.schema_version));
+            self.add_record(record.clone()).await?;
+        }
+
+        Ok(Some(record))
+    }
+
+    async fn get_record_raw(&self, key: &str) -> Result<Option<Record>, DatabaseError> {
+        if let Some(metadata) = &self.metadata {
+            if metadata.deleted.contains(key) {
+                return Ok(None);
+            }
+        }
+
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.cache {
+            if let Some((_, record)) = cache.get_record(key)? {
+                return Ok(Some(record));
+            }
+        }
+
+        if let Some(metadata) = &self.metadata {
+            if let Some(height) = metadata.index.get(key) {
+                log_with_timestamp(&format!("Looking up record '{}' via index at height {}", key, height));
+                if let Ok(blobs) = self.backend.get_at(*height).await {
+                    for payload in &blobs {
+                        if let Ok(record) = serde_json::from_slice::<Record>(payload) {
+                            if record.key == key {
+                                #[cfg(feature = "cache")]
+                                if let Some(cache) = &self.cache {
+                                    cache.put_record(key, *height, &record)?;
+                                }
+                                return Ok(Some(record));
+                            }
+                        }
+                    }
+                }
+                // The index pointed at a height but the record wasn't there
+                // (e.g. a stale snapshot) or the fetch itself failed — fall
+                // through to the linear scan.
+            }
+        }
+
+        log_with_timestamp(&format!("Key '{}' not in index, falling back to a full scan", key));
+        self.scan_for_record(key).await
+    }
+
+    /// O(chain length) fallback used only when a key isn't in the index.
+    async fn scan_for_record(&self, key: &str) -> Result<Option<Record>, DatabaseError> {
+        let latest_height = self.backend.head().await?;
+        let start_height = self.metadata.as_ref().map(|m| m.start_height).unwrap_or(1);
+
         for height in (start_height..=latest_height).rev() {
-            match self.get_blobs_at_height(height).await {
+            match self.backend.get_at(height).await {
                 Ok(blobs) => {
-                    for blob in blobs {
-                        // Try to parse as record
-                        if let Ok(record) = serde_json::from_slice::<Record>(&blob.data) {
+                    for payload in &blobs {
+                        if let Ok(record) = serde_json::from_slice::<Record>(payload) {
                             if record.key == key {
                                 log_with_timestamp(&format!("Found record with key '{}' at height {}", key, height));
                                 return Ok(Some(record));
                             }
+                        } else if let Ok(tombstone) = serde_json::from_slice::<Tombstone>(payload) {
+                            if tombstone.key == key {
+                                log_with_timestamp(&format!("Found tombstone for key '{}' at height {}", key, height));
+                                return Ok(None);
+                            }
                         }
                     }
                 }
-                Err(_) => continue, // Error retrieving blobs, try next height
+                Err(_) => continue,
             }
         }
 
@@ -256,46 +574,272 @@ impl DatabaseClient {
 
     pub async fn list_records(&self) -> Result<Vec<Record>, DatabaseError> {
         log_with_timestamp("Listing all records");
-        
-        let latest_height = self.client.header_local_head()
-            .await
-            .map_err(|e| DatabaseError::CelestiaError(e.to_string()))?
-            .height()
-            .value();
-        
-        // Get start height from metadata if available
-        let start_height = if let Some(metadata) = &self.metadata {
-            metadata.start_height
-        } else {
-            1 // Fallback to beginning if no metadata (shouldn't happen)
+
+        let metadata = match &self.metadata {
+            Some(metadata) => metadata,
+            None => return Ok(Vec::new()),
         };
-        
-        log_with_timestamp(&format!(
-            "Listing all records (database start: {}, current height: {})", 
-            start_height, latest_height
-        ));
-        
-        let mut records_map: HashMap<String, Record> = HashMap::new();
-        
-        // Search from start height to the latest height
-        for height in (start_height..=latest_height).rev() {
-            match self.get_blobs_at_height(height).await {
-                Ok(blobs) => {
-                    for blob in blobs {
-                        // Try to parse as record
-                        if let Ok(record) = serde_json::from_slice::<Record>(&blob.data) {
-                            // Only add if we haven't seen this key before (since we're going backwards)
-                            if !records_map.contains_key(&record.key) {
-                                records_map.insert(record.key.clone(), record);
+
+        // Group keys by the height their blob lives at so each height is
+        // only fetched once, regardless of how many keys landed there.
+        let mut keys_by_height: HashMap<u64, Vec<&str>> = HashMap::new();
+        for (key, height) in &metadata.index {
+            if metadata.deleted.contains(key) {
+                continue;
+            }
+            keys_by_height.entry(*height).or_default().push(key.as_str());
+        }
+
+        let mut records = Vec::new();
+        for (height, keys) in keys_by_height {
+            let blobs = match self.backend.get_at(height).await {
+                Ok(blobs) => blobs,
+                Err(_) => continue,
+            };
+            for payload in &blobs {
+                if let Ok(mut record) = serde_json::from_slice::<Record>(payload) {
+                    if keys.contains(&record.key.as_str()) {
+                        self.migrator.migrate(&mut record);
+                        records.push(record);
+                    }
+                }
+            }
+        }
+
+        log_with_timestamp(&format!("Found {} records", records.len()));
+        Ok(records)
+    }
+
+    /// Consumes this client and starts polling the backend for new
+    /// heights, streaming `RecordEvent`s (adds/updates and deletes) to the
+    /// returned receiver as they show up. Useful for consumers (like the
+    /// chess `GameState` front-end) that want to react to remote writes
+    /// instead of polling `list_records`.
+    ///
+    /// `StorageBackend` only exposes `submit`/`get_at`/`head`, with no
+    /// subscription primitive of its own, so this drives itself off
+    /// `head()` on an interval rather than a real Celestia header
+    /// subscription; swap this loop out if `StorageBackend` ever grows one.
+    pub fn subscribe(mut self) -> mpsc::Receiver<RecordEvent>
+    where
+        B: 'static,
+    {
+        let (tx, rx) = mpsc::channel(100);
+
+        tokio::spawn(async move {
+            loop {
+                match self.poll_new_records().await {
+                    Ok(events) => {
+                        for event in events {
+                            if tx.send(event).await.is_err() {
+                                // Receiver dropped; stop polling.
+                                return;
                             }
                         }
                     }
+                    Err(e) => {
+                        log_with_timestamp(&format!("subscribe: poll failed: {}", e));
+                    }
+                }
+
+                tokio::time::sleep(SUBSCRIBE_POLL_INTERVAL).await;
+            }
+        });
+
+        rx
+    }
+
+    /// Fetches every height since `last_seen_height`, folds any new
+    /// records/tombstones into the index (and adopts any newer metadata
+    /// snapshot), and returns just the events that were newly included.
+    async fn poll_new_records(&mut self) -> Result<Vec<RecordEvent>, DatabaseError> {
+        let latest_height = self.backend.head().await?;
+        if latest_height <= self.last_seen_height {
+            return Ok(Vec::new());
+        }
+
+        let mut new_events = Vec::new();
+
+        for height in (self.last_seen_height + 1)..=latest_height {
+            let payloads = match self.backend.get_at(height).await {
+                Ok(payloads) => payloads,
+                Err(_) => continue,
+            };
+
+            for payload in &payloads {
+                if let Ok(metadata) = serde_json::from_slice::<DatabaseMetadata>(payload) {
+                    let is_newer = self.metadata.as_ref()
+                        .map(|current| metadata.last_updated > current.last_updated)
+                        .unwrap_or(true);
+                    if is_newer {
+                        self.metadata = Some(metadata);
+                    }
+                } else if let Ok(record) = serde_json::from_slice::<Record>(payload) {
+                    if let Some(metadata) = &mut self.metadata {
+                        metadata.add_record(record.key.clone(), height, record.data.len() as u64);
+                    }
+                    new_events.push(RecordEvent::Added(record));
+                } else if let Ok(tombstone) = serde_json::from_slice::<Tombstone>(payload) {
+                    if let Some(metadata) = &mut self.metadata {
+                        metadata.delete_record(&tombstone.key);
+                    }
+                    new_events.push(RecordEvent::Deleted(tombstone.key));
                 }
-                Err(_) => continue, // Error retrieving blobs, try next height
             }
         }
 
-        log_with_timestamp(&format!("Found {} records", records_map.len()));
-        Ok(records_map.into_values().collect())
+        self.last_seen_height = latest_height;
+        Ok(new_events)
+    }
+}
+
+/// An event streamed by `subscribe`: a key was written (added or updated),
+/// or a key was deleted.
+pub enum RecordEvent {
+    Added(Record),
+    Deleted(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryBackend;
+
+    async fn client() -> DatabaseClient<MemoryBackend> {
+        DatabaseClient::new(MemoryBackend::new(), None, Quota::default()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn add_and_get_record() {
+        let mut db = client().await;
+        db.add_record(Record::new("k".to_string(), b"v".to_vec())).await.unwrap();
+
+        let record = db.get_record("k").await.unwrap().unwrap();
+        assert_eq!(record.data, b"v".to_vec());
+    }
+
+    #[tokio::test]
+    async fn get_missing_record_returns_none() {
+        let db = client().await;
+        assert!(db.get_record("missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn update_record_replaces_data() {
+        let mut db = client().await;
+        db.add_record(Record::new("k".to_string(), b"v1".to_vec())).await.unwrap();
+        db.update_record("k", b"v2".to_vec()).await.unwrap();
+
+        let record = db.get_record("k").await.unwrap().unwrap();
+        assert_eq!(record.data, b"v2".to_vec());
+    }
+
+    #[tokio::test]
+    async fn update_missing_record_errors() {
+        let mut db = client().await;
+        assert!(db.update_record("missing", b"v".to_vec()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn delete_record_hides_it_from_reads() {
+        let mut db = client().await;
+        db.add_record(Record::new("k".to_string(), b"v".to_vec())).await.unwrap();
+        db.delete_record("k").await.unwrap();
+
+        assert!(db.get_record("k").await.unwrap().is_none());
+        assert!(db.list_records().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn readding_a_deleted_key_makes_it_visible_again() {
+        let mut db = client().await;
+        db.add_record(Record::new("k".to_string(), b"v1".to_vec())).await.unwrap();
+        db.delete_record("k").await.unwrap();
+        db.add_record(Record::new("k".to_string(), b"v2".to_vec())).await.unwrap();
+
+        let record = db.get_record("k").await.unwrap().unwrap();
+        assert_eq!(record.data, b"v2".to_vec());
+        assert!(db.list_records().await.unwrap().iter().any(|r| r.key == "k"));
+    }
+
+    #[tokio::test]
+    async fn compact_drops_tombstoned_keys_from_the_index() {
+        let mut db = client().await;
+        db.add_record(Record::new("k".to_string(), b"v".to_vec())).await.unwrap();
+        db.delete_record("k").await.unwrap();
+        db.compact().await.unwrap();
+
+        assert!(!db.metadata.as_ref().unwrap().index.contains_key("k"));
+    }
+
+    #[tokio::test]
+    async fn quota_rejects_a_new_key_past_max_records() {
+        let quota = Quota { max_records: Some(1), max_bytes: None };
+        let mut db = DatabaseClient::new(MemoryBackend::new(), None, quota).await.unwrap();
+
+        db.add_record(Record::new("a".to_string(), b"v".to_vec())).await.unwrap();
+        assert!(db.add_record(Record::new("b".to_string(), b"v".to_vec())).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn quota_allows_updating_an_existing_key_past_max_records() {
+        let quota = Quota { max_records: Some(1), max_bytes: None };
+        let mut db = DatabaseClient::new(MemoryBackend::new(), None, quota).await.unwrap();
+
+        db.add_record(Record::new("a".to_string(), b"v".to_vec())).await.unwrap();
+        assert!(db.add_record(Record::new("a".to_string(), b"v2".to_vec())).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn add_record_stamps_metadata_with_the_live_target_version() {
+        let migrator = crate::migrations::Migrator::new(vec![|record| record.data.push(b'!')]);
+        let mut db = DatabaseClient::new(MemoryBackend::new(), None, Quota::default())
+            .await
+            .unwrap()
+            .with_migrator(migrator);
+
+        db.add_record(Record::new("k".to_string(), b"v".to_vec())).await.unwrap();
+
+        assert_eq!(db.metadata.as_ref().unwrap().schema_version, db.migrator.target_version());
+    }
+
+    #[tokio::test]
+    async fn repair_counters_recomputes_from_scratch() {
+        let mut db = client().await;
+        db.add_record(Record::new("a".to_string(), b"v".to_vec())).await.unwrap();
+        db.add_record(Record::new("b".to_string(), b"v".to_vec())).await.unwrap();
+        db.delete_record("a").await.unwrap();
+
+        db.repair_counters().await.unwrap();
+
+        let metadata = db.metadata.as_ref().unwrap();
+        assert_eq!(metadata.record_count, 1);
+        assert!(metadata.deleted.contains("a"));
+    }
+
+    #[tokio::test]
+    async fn subscribe_emits_added_and_deleted_events() {
+        let mut db = client().await;
+        db.add_record(Record::new("k".to_string(), b"v".to_vec())).await.unwrap();
+        db.delete_record("k").await.unwrap();
+
+        let mut rx = db.subscribe();
+
+        let mut saw_added = false;
+        let mut saw_deleted = false;
+        for _ in 0..10 {
+            match rx.recv().await {
+                Some(RecordEvent::Added(record)) if record.key == "k" => saw_added = true,
+                Some(RecordEvent::Deleted(key)) if key == "k" => saw_deleted = true,
+                Some(_) => {}
+                None => break,
+            }
+            if saw_added && saw_deleted {
+                break;
+            }
+        }
+
+        assert!(saw_added);
+        assert!(saw_deleted);
     }
-} 
\ No newline at end of file
+}
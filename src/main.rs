@@ -1,10 +1,16 @@
+#[cfg(feature = "cache")]
+mod cache;
 mod database;
+mod migrations;
 mod schema;
+mod storage;
 
 use celestia_rpc::Client;
 use chrono::Local;
 use database::DatabaseClient;
 use schema::{DatabaseError, Record};
+use database::Quota;
+use storage::CelestiaBackend;
 use std::io::{self, BufRead, Write};
 use std::str::FromStr;
 
@@ -54,11 +60,11 @@ impl FromStr for Command {
     }
 }
 
-async fn handle_command(db: &mut DatabaseClient, cmd: Command) -> Result<(), DatabaseError> {
+async fn handle_command(db: &mut DatabaseClient<CelestiaBackend>, cmd: Command) -> Result<(), DatabaseError> {
     match cmd {
         Command::Add(key, value) => {
             log_with_timestamp(&format!("Adding record with key '{}'", key));
-            let record = Record::new(key, value);
+            let record = Record::new(key, value.into_bytes());
             db.add_record(record).await?;
             log_with_timestamp("Record added successfully");
         }
@@ -67,11 +73,9 @@ async fn handle_command(db: &mut DatabaseClient, cmd: Command) -> Result<(), Dat
             match db.get_record(&key).await? {
                 Some(record) => {
                     println!("Key: {}", record.key);
-                    println!("Value: {}", record.value);
+                    println!("Value: {}", String::from_utf8_lossy(&record.data));
                     println!("Created: {}", record.created_at);
-                    if let Some(updated) = record.updated_at {
-                        println!("Updated: {}", updated);
-                    }
+                    println!("Updated: {}", record.updated_at);
                 }
                 None => log_with_timestamp(&format!("No record found with key '{}'", key)),
             }
@@ -83,11 +87,9 @@ async fn handle_command(db: &mut DatabaseClient, cmd: Command) -> Result<(), Dat
             } else {
                 for record in records {
                     println!("Key: {}", record.key);
-                    println!("Value: {}", record.value);
+                    println!("Value: {}", String::from_utf8_lossy(&record.data));
                     println!("Created: {}", record.created_at);
-                    if let Some(updated) = record.updated_at {
-                        println!("Updated: {}", updated);
-                    }
+                    println!("Updated: {}", record.updated_at);
                     println!("---");
                 }
             }
@@ -161,7 +163,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .map_err(|e| Box::new(DatabaseError::CelestiaError(e.to_string())) as Box<dyn std::error::Error>)?;
     log_with_timestamp("Successfully connected to Celestia node");
     
-    let mut db_client = DatabaseClient::new(client, namespace_bytes, None, search_limit).await?;
+    let mut db_client = DatabaseClient::connect_celestia(client, namespace_bytes, search_limit, Quota::default()).await?;
     log_with_timestamp("Database client initialized");
 
     println!("\nAvailable commands:");
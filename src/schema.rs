@@ -8,21 +8,36 @@ pub enum DatabaseError {
     #[error("Record not found: {0}")]
     RecordNotFound(String),
     #[error("Serialization error: {0}")]
-    SerializationError(#[from] serde_json::Error),
+    SerializationError(String),
     #[error("Celestia error: {0}")]
     CelestiaError(String),
     #[error("Database error: {0}")]
     DatabaseError(String),
+    #[error("Invalid namespace: {0}")]
+    InvalidNamespace(String),
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
 }
 
 /// Result type for database operations
 pub type DatabaseResult<T> = Result<T, DatabaseError>;
 
+/// The base schema version. `Record::new` and `DatabaseMetadata::new` stamp
+/// it, and a [`crate::migrations::Migrator`] with no migrations targets it;
+/// each migration a `Migrator` holds moves a record one version past it, up
+/// to the migrator's `target_version()`.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
 /// Represents a record in the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Record {
-    /// Unique identifier for the record
-    pub id: String,
+    /// The key this record is addressed by
+    pub key: String,
+    /// The schema version this record was written under. Older blobs are
+    /// upgraded to the migrator's target version in memory as they're read back
+    /// (see [`crate::migrations::Migrator`]); Celestia blobs themselves are
+    /// immutable.
+    pub schema_version: u32,
     /// Timestamp when the record was created
     pub created_at: DateTime<Utc>,
     /// Timestamp of the last update
@@ -32,11 +47,13 @@ pub struct Record {
 }
 
 impl Record {
-    /// Creates a new record with the given data
-    pub fn new(data: Vec<u8>) -> Self {
+    /// Creates a new record with the given key and data, stamped at the
+    /// current schema version
+    pub fn new(key: String, data: Vec<u8>) -> Self {
         let now = Utc::now();
         Self {
-            id: uuid::Uuid::new_v4().to_string(),
+            key,
+            schema_version: CURRENT_SCHEMA_VERSION,
             created_at: now,
             updated_at: now,
             data,
@@ -50,42 +67,97 @@ impl Record {
     }
 }
 
+/// A tombstone blob submitted in place of a record to mark `key` as
+/// deleted. Distinct from `Record` so a linear scan over raw blobs can't
+/// confuse a deletion marker for live data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tombstone {
+    /// The key being deleted
+    pub key: String,
+    /// Always `true`; present so the shape is self-describing on the wire
+    pub deleted: bool,
+    /// When the deletion was submitted
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl Tombstone {
+    /// Creates a tombstone for `key`
+    pub fn new(key: String) -> Self {
+        Self {
+            key,
+            deleted: true,
+            deleted_at: Utc::now(),
+        }
+    }
+}
+
 /// Metadata for the database, stored in the first blob
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseMetadata {
+    /// Height that scans for this database's blobs start from: the height
+    /// of its first metadata blob, or, for a client warmed from a local
+    /// cache, the cache's synced head (1 for an empty cache)
+    pub start_height: u64,
     /// Number of records in the database
     pub record_count: u64,
-    /// Mapping of record IDs to their blob heights
+    /// Total size in bytes of all live records' data
+    pub total_bytes: u64,
+    /// Mapping of record keys to the height of the blob that holds them
     pub index: std::collections::HashMap<String, u64>,
-    /// Set of deleted record IDs
+    /// Mapping of record keys to their data size in bytes, kept alongside
+    /// `index` so `delete_record` can decrement `total_bytes` correctly
+    pub sizes: std::collections::HashMap<String, u64>,
+    /// Set of deleted record keys
     pub deleted: std::collections::HashSet<String>,
     /// Last update timestamp
     pub last_updated: DateTime<Utc>,
+    /// The schema version this binary targets. Carried in metadata (rather
+    /// than inferred from records) so `discover_database` can warn when it
+    /// finds a snapshot newer than the running binary even before reading
+    /// any records.
+    pub schema_version: u32,
 }
 
 impl DatabaseMetadata {
-    /// Creates new empty metadata
-    pub fn new() -> Self {
+    /// Creates new empty metadata starting at `start_height`, targeting the
+    /// current schema version
+    pub fn new(start_height: u64) -> Self {
         Self {
+            start_height,
             record_count: 0,
+            total_bytes: 0,
             index: std::collections::HashMap::new(),
+            sizes: std::collections::HashMap::new(),
             deleted: std::collections::HashSet::new(),
             last_updated: Utc::now(),
+            schema_version: CURRENT_SCHEMA_VERSION,
         }
     }
 
-    /// Adds a record to the metadata
-    pub fn add_record(&mut self, record_id: String, height: u64) {
-        self.record_count += 1;
-        self.index.insert(record_id, height);
+    /// Records a key as living in the blob at `height`, with a data size
+    /// of `size` bytes. Clears any prior tombstone for `key`, since a
+    /// record written after a delete (e.g. a game restarting under a
+    /// reused key) is live again.
+    pub fn add_record(&mut self, key: String, height: u64, size: u64) {
+        if let Some(old_size) = self.sizes.insert(key.clone(), size) {
+            self.total_bytes = self.total_bytes.saturating_sub(old_size);
+        } else {
+            self.record_count += 1;
+        }
+        self.total_bytes += size;
+        self.index.insert(key.clone(), height);
+        self.deleted.remove(&key);
         self.last_updated = Utc::now();
     }
 
-    /// Marks a record as deleted
-    pub fn delete_record(&mut self, record_id: &str) -> bool {
-        if self.index.remove(record_id).is_some() {
+    /// Marks a key as deleted
+    pub fn delete_record(&mut self, key: &str) -> bool {
+        if self.index.remove(key).is_some() {
             self.record_count -= 1;
-            self.deleted.insert(record_id.to_string());
+            if let Some(size) = self.sizes.remove(key) {
+                self.total_bytes = self.total_bytes.saturating_sub(size);
+            }
+            self.deleted.insert(key.to_string());
             self.last_updated = Utc::now();
             true
         } else {
@@ -93,17 +165,17 @@ impl DatabaseMetadata {
         }
     }
 
-    /// Checks if a record exists and is not deleted
-    pub fn record_exists(&self, record_id: &str) -> bool {
-        self.index.contains_key(record_id) && !self.deleted.contains(record_id)
+    /// Checks if a key exists and is not deleted
+    pub fn record_exists(&self, key: &str) -> bool {
+        self.index.contains_key(key) && !self.deleted.contains(key)
     }
 
-    /// Gets the height for a record if it exists and is not deleted
-    pub fn get_record_height(&self, record_id: &str) -> Option<u64> {
-        if self.deleted.contains(record_id) {
+    /// Gets the height for a key if it exists and is not deleted
+    pub fn get_record_height(&self, key: &str) -> Option<u64> {
+        if self.deleted.contains(key) {
             None
         } else {
-            self.index.get(record_id).copied()
+            self.index.get(key).copied()
         }
     }
 } 
\ No newline at end of file